@@ -0,0 +1,614 @@
+/* currency module
+ *
+ * Enums as a "choice" of a fixed set of values:
+ *  - Currency : one of the supported ISO 4217 currencies
+ *  - Locale   : how a Money value is displayed ( separators, symbol position )
+ *  - Rounding : rule used when a converted amount falls between two minor units
+ *
+ * Money(amount, Currency) stores the amount in minor units ( cents, pence, ... ) as i64 so
+ * adding/comparing prices never suffers f64 rounding errors. f64 is only used for the
+ * exchange rate itself while converting.
+ *
+ * Exchange rates are loaded from a plain text file, one rate per line:
+ *      # from to rate
+ *      USD EUR 0.92
+ */
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Currency {
+    Usd,
+    Eur,
+    Gbp,
+    Jpy,
+    Inr,
+}
+
+impl Currency {
+    pub const ALL: [Currency; 5] = [
+        Currency::Usd,
+        Currency::Eur,
+        Currency::Gbp,
+        Currency::Jpy,
+        Currency::Inr,
+    ];
+
+    // ISO 4217 alphabetic code
+    pub fn code(&self) -> &'static str {
+        match self {
+            Currency::Usd => "USD",
+            Currency::Eur => "EUR",
+            Currency::Gbp => "GBP",
+            Currency::Jpy => "JPY",
+            Currency::Inr => "INR",
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Currency::Usd => "$",
+            Currency::Eur => "€",
+            Currency::Gbp => "£",
+            Currency::Jpy => "¥",
+            Currency::Inr => "₹",
+        }
+    }
+
+    // number of digits after the decimal point ( JPY has no minor unit )
+    pub fn minor_digits(&self) -> u32 {
+        match self {
+            Currency::Jpy => 0,
+            _ => 2,
+        }
+    }
+
+    // the locale a currency is usually written in, used by Display
+    pub fn default_locale(&self) -> Locale {
+        match self {
+            Currency::Usd => Locale::EnUs,
+            Currency::Eur => Locale::DeDe,
+            Currency::Gbp => Locale::EnGb,
+            Currency::Jpy => Locale::JaJp,
+            Currency::Inr => Locale::EnIn,
+        }
+    }
+
+    fn minor_factor(&self) -> i64 {
+        10_i64.pow(self.minor_digits())
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+impl FromStr for Currency {
+    type Err = CurrencyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let code = s.trim().to_ascii_uppercase();
+        Currency::ALL
+            .iter()
+            .copied()
+            .find(|c| c.code() == code)
+            .ok_or(CurrencyError::UnknownCurrency(s.trim().to_string()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    EnUs, // $1,234.56
+    EnGb, // £1,234.56
+    EnIn, // ₹12,34,567.89 ( lakh / crore grouping )
+    DeDe, // 1.234,56 €
+    FrFr, // 1 234,56 €
+    JaJp, // ¥1,235
+}
+
+impl Locale {
+    // ( thousands separator, decimal separator )
+    fn separators(&self) -> (&'static str, &'static str) {
+        match self {
+            Locale::EnUs | Locale::EnGb | Locale::EnIn | Locale::JaJp => (",", "."),
+            Locale::DeDe => (".", ","),
+            Locale::FrFr => ("\u{202f}", ","),
+        }
+    }
+
+    // gap placed before a trailing symbol, None when the symbol goes in front
+    // ( no-break spaces so a line break never separates the amount from the symbol )
+    fn symbol_after(&self) -> Option<&'static str> {
+        match self {
+            Locale::DeDe => Some("\u{a0}"),
+            Locale::FrFr => Some("\u{202f}"),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    HalfUp,   // 0.5 rounds away from zero
+    HalfEven, // 0.5 rounds to the nearest even value ( banker's rounding )
+    Down,     // always truncate towards zero
+}
+
+impl Rounding {
+    fn apply(&self, value: f64) -> i64 {
+        match self {
+            Rounding::HalfUp => value.round() as i64,
+            Rounding::Down => value.trunc() as i64,
+            Rounding::HalfEven => {
+                let floor = value.floor();
+                let diff = value - floor;
+                // treat values within float noise of .5 as an exact tie, the noise grows with
+                // the size of the value so the tolerance is a few ulps relative to it
+                let tolerance = 8.0 * f64::EPSILON * value.abs().max(1.0);
+                if (diff - 0.5).abs() <= tolerance {
+                    if (floor as i64).rem_euclid(2) == 0 {
+                        floor as i64
+                    } else {
+                        floor as i64 + 1
+                    }
+                } else {
+                    value.round() as i64
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CurrencyError {
+    UnknownCurrency(String),
+    MissingRate(Currency, Currency),
+    InvalidRate { line: usize, text: String },
+    Io(std::io::Error),
+}
+
+impl fmt::Display for CurrencyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CurrencyError::UnknownCurrency(code) => write!(f, "unknown currency code '{}'", code),
+            CurrencyError::MissingRate(from, to) => {
+                write!(f, "no exchange rate from {} to {}", from, to)
+            }
+            CurrencyError::InvalidRate { line, text } => {
+                write!(f, "invalid rate on line {}: '{}'", line, text)
+            }
+            CurrencyError::Io(e) => write!(f, "failed to read rate file: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for CurrencyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CurrencyError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for CurrencyError {
+    fn from(e: std::io::Error) -> Self {
+        CurrencyError::Io(e)
+    }
+}
+
+// Exchange-rate table: 1 unit of `from` = rate units of `to`
+#[derive(Debug, Default)]
+pub struct RateTable {
+    rates: HashMap<(Currency, Currency), f64>,
+}
+
+impl RateTable {
+    pub fn new() -> RateTable {
+        RateTable::default()
+    }
+
+    pub fn from_file(path: &str) -> Result<RateTable, CurrencyError> {
+        let text = fs::read_to_string(path)?;
+        RateTable::parse(&text)
+    }
+
+    // parse "FROM TO RATE" lines, blank lines and lines starting with '#' are skipped
+    pub fn parse(text: &str) -> Result<RateTable, CurrencyError> {
+        let mut table = RateTable::new();
+        for (idx, raw) in text.lines().enumerate() {
+            let line = raw.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || CurrencyError::InvalidRate {
+                line: idx + 1,
+                text: line.to_string(),
+            };
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() != 3 {
+                return Err(invalid());
+            }
+            let from: Currency = fields[0].parse()?;
+            let to: Currency = fields[1].parse()?;
+            let rate: f64 = fields[2].parse().map_err(|_| invalid())?;
+            if !rate.is_finite() || rate <= 0.0 {
+                return Err(invalid());
+            }
+            table.set_rate(from, to, rate);
+        }
+        Ok(table)
+    }
+
+    pub fn set_rate(&mut self, from: Currency, to: Currency, rate: f64) {
+        self.rates.insert((from, to), rate);
+    }
+
+    // a direct rate wins, then the inverse of the opposite direction, and last a cross rate
+    // through a common currency: rates.txt only lists USD pairs, EUR -> GBP goes via USD
+    pub fn rate(&self, from: Currency, to: Currency) -> Option<f64> {
+        self.direct_rate(from, to).or_else(|| {
+            Currency::ALL
+                .iter()
+                .find_map(|&via| Some(self.direct_rate(from, via)? * self.direct_rate(via, to)?))
+        })
+    }
+
+    fn direct_rate(&self, from: Currency, to: Currency) -> Option<f64> {
+        if from == to {
+            return Some(1.0);
+        }
+        self.rates
+            .get(&(from, to))
+            .copied()
+            .or_else(|| self.rates.get(&(to, from)).map(|r| 1.0 / r))
+    }
+}
+
+// Money(amount in minor units, currency)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Money(pub i64, pub Currency);
+
+impl Money {
+    // build from major units, e.g Money::new(12.5, Currency::Usd) == $12.50
+    pub fn new(amount: f64, currency: Currency) -> Money {
+        Money(
+            Rounding::HalfUp.apply(amount * currency.minor_factor() as f64),
+            currency,
+        )
+    }
+
+    pub fn convert(&self, to: Currency, rates: &RateTable) -> Result<Money, CurrencyError> {
+        self.convert_with(to, rates, Rounding::HalfEven)
+    }
+
+    pub fn convert_with(
+        &self,
+        to: Currency,
+        rates: &RateTable,
+        rounding: Rounding,
+    ) -> Result<Money, CurrencyError> {
+        let rate = rates
+            .rate(self.1, to)
+            .ok_or(CurrencyError::MissingRate(self.1, to))?;
+        let minor = self.0 as f64 * rate * to.minor_factor() as f64 / self.1.minor_factor() as f64;
+        Ok(Money(rounding.apply(minor), to))
+    }
+
+    pub fn format(&self, locale: Locale) -> String {
+        let (group_sep, decimal_sep) = locale.separators();
+        let digits = self.1.minor_digits();
+        let factor = self.1.minor_factor().unsigned_abs();
+        let abs = self.0.unsigned_abs();
+
+        let whole = group(&(abs / factor).to_string(), group_sep, locale);
+        let mut number = whole;
+        if digits > 0 {
+            number.push_str(decimal_sep);
+            number.push_str(&format!(
+                "{:0width$}",
+                abs % factor,
+                width = digits as usize
+            ));
+        }
+
+        let sign = if self.0 < 0 { "-" } else { "" };
+        match locale.symbol_after() {
+            Some(gap) => format!("{}{}{}{}", sign, number, gap, self.1.symbol()),
+            None => format!("{}{}{}", sign, self.1.symbol(), number),
+        }
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.format(self.1.default_locale()))
+    }
+}
+
+// insert the thousands separator into a string of digits
+fn group(digits: &str, sep: &str, locale: Locale) -> String {
+    // en-IN groups the last three digits, then every two: 12,34,567
+    let (first, rest) = if locale == Locale::EnIn {
+        (3, 2)
+    } else {
+        (3, 3)
+    };
+
+    let mut groups: Vec<&str> = Vec::new();
+    let mut end = digits.len();
+    let mut size = first;
+    while end > size {
+        groups.push(&digits[end - size..end]);
+        end -= size;
+        size = rest;
+    }
+    groups.push(&digits[..end]);
+    groups.reverse();
+    groups.join(sep)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the same table the demo loads
+    fn shipped_rates() -> RateTable {
+        RateTable::parse(include_str!("../rates.txt")).unwrap()
+    }
+
+    fn round_trip(start: Money, via: Currency, rates: &RateTable) -> Money {
+        let there = start.convert(via, rates).unwrap();
+        there.convert(start.1, rates).unwrap()
+    }
+
+    #[test]
+    fn usd_eur_usd_round_trip_drifts_at_most_one_cent() {
+        let rates = shipped_rates();
+        // the intermediate EUR amount is rounded to whole cents, so coming back can be off
+        // by one: this is the case printed by the demo
+        let price = Money::new(1234.56, Currency::Usd);
+        assert_eq!(
+            price.convert(Currency::Eur, &rates).unwrap(),
+            Money(113580, Currency::Eur)
+        );
+        assert_eq!(
+            round_trip(price, Currency::Eur, &rates),
+            Money(123457, Currency::Usd)
+        );
+
+        for cents in (1..2_000_000).step_by(997) {
+            let back = round_trip(Money(cents, Currency::Usd), Currency::Eur, &rates);
+            assert!(
+                (back.0 - cents).abs() <= 1,
+                "{} cents came back as {}",
+                cents,
+                back.0
+            );
+        }
+    }
+
+    #[test]
+    fn usd_jpy_usd_round_trip_drifts_at_most_one_cent() {
+        let rates = shipped_rates();
+        let price = Money::new(1234.56, Currency::Usd);
+        assert_eq!(
+            price.convert(Currency::Jpy, &rates).unwrap(),
+            Money(186912, Currency::Jpy)
+        );
+        assert_eq!(round_trip(price, Currency::Jpy, &rates), price);
+
+        // JPY has no minor unit, one yen is less than a cent so the drift stays within a cent
+        for cents in (1..2_000_000).step_by(997) {
+            let back = round_trip(Money(cents, Currency::Usd), Currency::Jpy, &rates);
+            assert!(
+                (back.0 - cents).abs() <= 1,
+                "{} cents came back as {}",
+                cents,
+                back.0
+            );
+        }
+    }
+
+    #[test]
+    fn rounding_on_ties() {
+        assert_eq!(Rounding::HalfEven.apply(2.5), 2);
+        assert_eq!(Rounding::HalfEven.apply(3.5), 4);
+        assert_eq!(Rounding::HalfEven.apply(-2.5), -2);
+        assert_eq!(Rounding::HalfEven.apply(-3.5), -4);
+
+        assert_eq!(Rounding::HalfUp.apply(2.5), 3);
+        assert_eq!(Rounding::HalfUp.apply(3.5), 4);
+        assert_eq!(Rounding::HalfUp.apply(-2.5), -3);
+        assert_eq!(Rounding::HalfUp.apply(-3.5), -4);
+
+        assert_eq!(Rounding::Down.apply(2.5), 2);
+        assert_eq!(Rounding::Down.apply(2.7), 2);
+        assert_eq!(Rounding::Down.apply(-2.5), -2);
+        assert_eq!(Rounding::Down.apply(-2.7), -2);
+    }
+
+    #[test]
+    fn rounding_off_ties() {
+        assert_eq!(Rounding::HalfEven.apply(2.6), 3);
+        assert_eq!(Rounding::HalfEven.apply(-2.6), -3);
+        assert_eq!(Rounding::HalfUp.apply(2.4), 2);
+        // close to .5 but not a tie
+        assert_eq!(Rounding::HalfEven.apply(3.4999999999), 3);
+        assert_eq!(Rounding::HalfEven.apply(2.5000000001), 3);
+    }
+
+    #[test]
+    fn half_even_tie_on_large_amounts() {
+        // 1000000011 / 0.24 is exactly 4166666712.5, through the inverse rate f64 gives
+        // 4166666712.5000005 which must still count as a tie
+        let mut rates = RateTable::new();
+        rates.set_rate(Currency::Usd, Currency::Eur, 0.24);
+        let eur = Money(1_000_000_011, Currency::Eur);
+
+        assert_eq!(
+            eur.convert(Currency::Usd, &rates).unwrap(),
+            Money(4_166_666_712, Currency::Usd)
+        );
+        assert_eq!(
+            eur.convert_with(Currency::Usd, &rates, Rounding::HalfUp)
+                .unwrap(),
+            Money(4_166_666_713, Currency::Usd)
+        );
+    }
+
+    #[test]
+    fn parse_skips_comments_and_blank_lines() {
+        let text = "# from to rate\n\n   \nUSD EUR 0.5\n  # indented comment\nGBP JPY 200\n";
+        let rates = RateTable::parse(text).unwrap();
+        assert_eq!(rates.rate(Currency::Usd, Currency::Eur), Some(0.5));
+        assert_eq!(rates.rate(Currency::Gbp, Currency::Jpy), Some(200.0));
+        // codes are case-insensitive
+        let rates = RateTable::parse("usd inr 80").unwrap();
+        assert_eq!(rates.rate(Currency::Usd, Currency::Inr), Some(80.0));
+    }
+
+    #[test]
+    fn parse_reports_bad_line_number() {
+        for bad in [
+            "USD EUR",
+            "USD EUR 0.9 extra",
+            "USD EUR abc",
+            "USD EUR -1",
+            "USD EUR 0",
+        ] {
+            let text = format!("# header\n\nUSD GBP 0.8\n{}\n", bad);
+            match RateTable::parse(&text) {
+                Err(CurrencyError::InvalidRate { line, text }) => {
+                    assert_eq!(line, 4);
+                    assert_eq!(text, bad);
+                }
+                other => panic!("expected InvalidRate for '{}', got {:?}", bad, other),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_rejects_unknown_codes() {
+        match RateTable::parse("USD XYZ 1.0") {
+            Err(CurrencyError::UnknownCurrency(code)) => assert_eq!(code, "XYZ"),
+            other => panic!("expected UnknownCurrency, got {:?}", other),
+        }
+        assert!(matches!(
+            "abc".parse::<Currency>(),
+            Err(CurrencyError::UnknownCurrency(_))
+        ));
+    }
+
+    #[test]
+    fn inverse_rate_lookup() {
+        let mut rates = RateTable::new();
+        rates.set_rate(Currency::Usd, Currency::Eur, 0.8);
+        assert_eq!(rates.rate(Currency::Usd, Currency::Eur), Some(0.8));
+        assert_eq!(rates.rate(Currency::Eur, Currency::Usd), Some(1.25));
+        assert_eq!(rates.rate(Currency::Gbp, Currency::Gbp), Some(1.0));
+
+        // a direct rate wins over the inverse of the other direction
+        rates.set_rate(Currency::Eur, Currency::Usd, 1.3);
+        assert_eq!(rates.rate(Currency::Eur, Currency::Usd), Some(1.3));
+    }
+
+    #[test]
+    fn cross_rate_through_common_currency() {
+        let rates = shipped_rates();
+        let eur_gbp = rates.rate(Currency::Eur, Currency::Gbp).unwrap();
+        assert!((eur_gbp - 0.79 / 0.92).abs() < 1e-12);
+        let gbp_inr = rates.rate(Currency::Gbp, Currency::Inr).unwrap();
+        assert!((gbp_inr - 83.30 / 0.79).abs() < 1e-12);
+
+        // every pair is reachable with the shipped table
+        for from in Currency::ALL {
+            for to in Currency::ALL {
+                assert!(
+                    Money(100, from).convert(to, &rates).is_ok(),
+                    "{} -> {}",
+                    from,
+                    to
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn missing_rate_is_an_error() {
+        let mut rates = RateTable::new();
+        rates.set_rate(Currency::Usd, Currency::Eur, 0.9);
+        match Money(100, Currency::Gbp).convert(Currency::Jpy, &rates) {
+            Err(CurrencyError::MissingRate(from, to)) => {
+                assert_eq!((from, to), (Currency::Gbp, Currency::Jpy))
+            }
+            other => panic!("expected MissingRate, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn format_per_locale() {
+        let amount = Money(123456, Currency::Usd);
+        assert_eq!(amount.format(Locale::EnUs), "$1,234.56");
+        assert_eq!(amount.to_string(), "$1,234.56");
+
+        let eur = Money(123456, Currency::Eur);
+        assert_eq!(eur.format(Locale::DeDe), "1.234,56\u{a0}€");
+        assert_eq!(eur.format(Locale::FrFr), "1\u{202f}234,56\u{202f}€");
+        assert_eq!(eur.to_string(), "1.234,56\u{a0}€");
+
+        assert_eq!(Money(5, Currency::Usd).to_string(), "$0.05");
+        assert_eq!(
+            Money(100_000_000, Currency::Usd).to_string(),
+            "$1,000,000.00"
+        );
+    }
+
+    #[test]
+    fn format_en_in_lakh_grouping() {
+        assert_eq!(Money(99_900, Currency::Inr).to_string(), "₹999.00");
+        assert_eq!(Money(100_000, Currency::Inr).to_string(), "₹1,000.00");
+        assert_eq!(Money(10_000_000, Currency::Inr).to_string(), "₹1,00,000.00");
+        assert_eq!(
+            Money(123_456_789, Currency::Inr).to_string(),
+            "₹12,34,567.89"
+        );
+        assert_eq!(
+            Money(123_456_789_012, Currency::Inr).to_string(),
+            "₹1,23,45,67,890.12"
+        );
+    }
+
+    #[test]
+    fn format_jpy_has_no_decimals() {
+        assert_eq!(Money(1235, Currency::Jpy).to_string(), "¥1,235");
+        assert_eq!(Money(7, Currency::Jpy).to_string(), "¥7");
+        assert_eq!(
+            Money::new(1234.5, Currency::Jpy),
+            Money(1235, Currency::Jpy)
+        );
+    }
+
+    #[test]
+    fn format_negative_amounts() {
+        assert_eq!(Money(-123456, Currency::Usd).to_string(), "-$1,234.56");
+        assert_eq!(Money(-5, Currency::Usd).to_string(), "-$0.05");
+        assert_eq!(
+            Money(-123456, Currency::Eur).format(Locale::DeDe),
+            "-1.234,56\u{a0}€"
+        );
+        assert_eq!(
+            Money(-123456, Currency::Eur).format(Locale::FrFr),
+            "-1\u{202f}234,56\u{202f}€"
+        );
+        assert_eq!(
+            Money(-123_456_789, Currency::Inr).to_string(),
+            "-₹12,34,567.89"
+        );
+        assert_eq!(Money(-1_234_567, Currency::Jpy).to_string(), "-¥1,234,567");
+    }
+}
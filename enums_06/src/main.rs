@@ -1,5 +1,60 @@
+/*
+ * enums
+ * currency module: Currency, Locale and Rounding are plain enums,
+ * CurrencyError is an enum carrying data for each failure kind.
+ */
+mod currency;
+
+use currency::{Currency, Locale, Money, RateTable, Rounding};
+
 fn main() {
     println!("enums !");
 
-    //-
+    //- currency conversion
+    // rates.txt lives next to Cargo.toml, run with `cargo run` from this directory
+    let rates = match RateTable::from_file("rates.txt") {
+        Ok(table) => table,
+        Err(e) => {
+            println!("{}, using built-in rates", e);
+            let mut table = RateTable::new();
+            table.set_rate(Currency::Usd, Currency::Eur, 0.92);
+            table
+        }
+    };
+
+    let price = Money::new(1234.56, Currency::Usd);
+    println!("price: {}", price);
+
+    for to in Currency::ALL {
+        match price.convert(to, &rates) {
+            Ok(converted) => println!("  {} -> {}", price, converted),
+            Err(e) => println!("  {}", e),
+        }
+    }
+
+    // round trip USD -> EUR -> USD: can be off by one minor unit since the EUR amount was
+    // rounded to whole cents in between
+    if let Ok(eur) = price.convert(Currency::Eur, &rates) {
+        match eur.convert(Currency::Usd, &rates) {
+            Ok(back) => println!("round trip: {} -> {} -> {}", price, eur, back),
+            Err(e) => println!("round trip: {}", e),
+        }
+    }
+
+    // a different rounding rule: truncate instead of banker's rounding
+    if let Ok(inr) = price.convert_with(Currency::Inr, &rates, Rounding::Down) {
+        println!("rounded down: {} -> {}", price, inr);
+    }
+
+    // same amount written for different locales
+    let eur = Money::new(1234.5, Currency::Eur);
+    for locale in [Locale::EnUs, Locale::DeDe, Locale::FrFr] {
+        println!("{:?}: {}", locale, eur.format(locale));
+    }
+    println!("EnIn: {}", Money::new(1234567.89, Currency::Inr));
+
+    match "XYZ".parse::<Currency>() {
+        Ok(c) => println!("parsed {}", c),
+        Err(e) => println!("error: {}", e),
+    }
 }